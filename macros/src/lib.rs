@@ -0,0 +1,270 @@
+//! Procedural macros for the `statemachine` crate.
+//!
+//! The crate currently exposes a single entry point, [`state_machine!`], which
+//! turns a declarative transition table into a
+//! [`BasicStateMachineBuilder`](../statemachine/machine/builder/struct.BasicStateMachineBuilder.html)
+//! wiring and validates the table's reachability at compile time.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Ident, Token,
+};
+
+/// A single `From -- Input --> To` rule from the transition table.
+struct Rule {
+    from: Ident,
+    input: Ident,
+    to: Ident,
+}
+
+impl Parse for Rule {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let from: Ident = input.parse()?;
+        // The `--` separating the source state from the input label.
+        input.parse::<Token![-]>()?;
+        input.parse::<Token![-]>()?;
+        let label: Ident = input.parse()?;
+        // The `-->` arrow: a bare `-` followed by the `->` token.
+        input.parse::<Token![-]>()?;
+        input.parse::<Token![->]>()?;
+        let to: Ident = input.parse()?;
+        Ok(Rule {
+            from,
+            input: label,
+            to,
+        })
+    }
+}
+
+/// The fully parsed `state_machine! { .. }` invocation.
+struct StateMachineDef {
+    state_ty: Ident,
+    input_ty: Ident,
+    initial: Ident,
+    terminal: Vec<Ident>,
+    rules: Vec<Rule>,
+}
+
+impl Parse for StateMachineDef {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut state_ty: Option<Ident> = None;
+        let mut input_ty: Option<Ident> = None;
+        let mut initial: Option<Ident> = None;
+        let mut terminal: Vec<Ident> = Vec::new();
+
+        // Header fields come first; each is `key: value;`. A rule starts with a
+        // state identifier that is *not* followed by a colon, which is how we
+        // tell the header apart from the table below it.
+        while input.peek(Ident) && input.peek2(Token![:]) {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![:]>()?;
+            match key.to_string().as_str() {
+                "state" => state_ty = Some(input.parse()?),
+                "input" => input_ty = Some(input.parse()?),
+                "initial" => initial = Some(input.parse()?),
+                "terminal" => {
+                    let content;
+                    bracketed!(content in input);
+                    let states: Punctuated<Ident, Token![,]> =
+                        content.parse_terminated(Ident::parse, Token![,])?;
+                    terminal = states.into_iter().collect();
+                }
+                other => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!(
+                            "unknown field `{other}`; expected `state`, `input`, `initial` or `terminal`"
+                        ),
+                    ));
+                }
+            }
+            input.parse::<Token![;]>()?;
+        }
+
+        let rules: Punctuated<Rule, Token![,]> =
+            input.parse_terminated(Rule::parse, Token![,])?;
+
+        let state_ty = state_ty.ok_or_else(|| input.error("missing `state:` field"))?;
+        let input_ty = input_ty.ok_or_else(|| input.error("missing `input:` field"))?;
+        let initial = initial.ok_or_else(|| input.error("missing `initial:` field"))?;
+
+        Ok(StateMachineDef {
+            state_ty,
+            input_ty,
+            initial,
+            terminal,
+            rules: rules.into_iter().collect(),
+        })
+    }
+}
+
+/// Builds a [`BasicStateMachineBuilder`] from a declarative transition table,
+/// rejecting tables with unreachable or dead states at compile time.
+///
+/// The table names the `state` and `input` enums, the `initial` state, and a
+/// list of `From -- Input --> To` rules. An optional `terminal` list marks the
+/// states that are allowed to have no outgoing transition:
+///
+/// ```ignore
+/// let sm = state_machine! {
+///     state: Stations;
+///     input: Train;
+///     initial: Shibuya;
+///     terminal: [Yoga];
+///     Shibuya        -- Local --> IkejiriOhashi,
+///     IkejiriOhashi  -- Local --> Sangendyaya,
+///     Sangendyaya    -- Local --> Yoga,
+/// }
+/// .build()
+/// .unwrap();
+/// ```
+///
+/// The macro expands to `BasicStateMachineBuilder::start().initial_state(..)
+/// .transition(..)`, where the transition is a `match` over the declared rules
+/// that falls back to `unreachable!()` for undeclared `(state, input)` pairs.
+///
+/// During expansion the declared rules are treated as a directed graph and two
+/// properties are checked, each reported against the offending state
+/// identifier so the table can be fixed in place:
+///
+/// * **reachability** — every declared state must be reachable from `initial`
+///   by a breadth-first walk of the rules; an isolated state is an error.
+/// * **productivity** — every reachable, non-`initial` state must have an
+///   outgoing rule unless it is listed in `terminal`; a dead end that is not a
+///   terminal is an error.
+#[proc_macro]
+pub fn state_machine(input: TokenStream) -> TokenStream {
+    let def = parse_macro_input!(input as StateMachineDef);
+
+    let StateMachineDef {
+        state_ty,
+        input_ty,
+        initial,
+        rules,
+        ..
+    } = &def;
+
+    // Any reachability violation is injected as a leading `compile_error!`
+    // statement rather than replacing the expansion outright, so the builder
+    // still type-checks and rustc reports only our diagnostics instead of a
+    // cascade of secondary parse errors at the call site.
+    let diagnostics = match validate(&def) {
+        Ok(()) => proc_macro2::TokenStream::new(),
+        Err(err) => err.to_compile_error(),
+    };
+
+    let arms = rules.iter().map(|rule| {
+        let Rule { from, input, to } = rule;
+        quote! { (#state_ty::#from, #input_ty::#input) => #state_ty::#to }
+    });
+
+    quote! {
+        {
+            #diagnostics
+            use ::statemachine::machine::builder::StateMachineBuilder as _;
+            ::statemachine::machine::builder::BasicStateMachineBuilder::start()
+                .initial_state(#state_ty::#initial)
+                .transition(|__state, __input| match (__state, __input) {
+                    #(#arms,)*
+                    _ => ::core::unreachable!(),
+                })
+        }
+    }
+    .into()
+}
+
+/// Runs the reachability and productivity checks over the declared table,
+/// accumulating every violation into a single error so the user sees them all
+/// at once rather than one rebuild at a time.
+fn validate(def: &StateMachineDef) -> syn::Result<()> {
+    // First occurrence of each state identifier, kept so diagnostics point at a
+    // real span in the user's table.
+    let mut spans: HashMap<String, Ident> = HashMap::new();
+    let mut note = |ident: &Ident| {
+        spans.entry(ident.to_string()).or_insert_with(|| ident.clone());
+    };
+    note(&def.initial);
+    for term in &def.terminal {
+        note(term);
+    }
+    for rule in &def.rules {
+        note(&rule.from);
+        note(&rule.to);
+    }
+
+    // Adjacency list over state names.
+    let mut edges: HashMap<String, Vec<String>> = HashMap::new();
+    for rule in &def.rules {
+        edges
+            .entry(rule.from.to_string())
+            .or_default()
+            .push(rule.to.to_string());
+    }
+
+    // Breadth-first walk from the initial state to collect what is reachable.
+    let mut reachable: HashSet<String> = HashSet::new();
+    let mut queue: VecDeque<String> = VecDeque::new();
+    reachable.insert(def.initial.to_string());
+    queue.push_back(def.initial.to_string());
+    while let Some(state) = queue.pop_front() {
+        if let Some(targets) = edges.get(&state) {
+            for target in targets {
+                if reachable.insert(target.clone()) {
+                    queue.push_back(target.clone());
+                }
+            }
+        }
+    }
+
+    let terminals: HashSet<String> = def.terminal.iter().map(|t| t.to_string()).collect();
+    let initial = def.initial.to_string();
+
+    let mut errors: Vec<syn::Error> = Vec::new();
+
+    // (a) Non-reachable states. Walk the identifiers in a stable order so the
+    // diagnostics are deterministic across builds.
+    let mut names: Vec<&String> = spans.keys().collect();
+    names.sort();
+    for name in &names {
+        if !reachable.contains(*name) {
+            let ident = &spans[*name];
+            errors.push(syn::Error::new(
+                ident.span(),
+                format!("state `{name}` is not reachable from the initial state `{initial}`"),
+            ));
+        }
+    }
+
+    // (b) Non-productive states: reachable, non-initial, not terminal, and with
+    // no outgoing rule.
+    for name in &names {
+        if **name == initial || !reachable.contains(*name) || terminals.contains(*name) {
+            continue;
+        }
+        let has_outgoing = edges.get(*name).is_some_and(|t| !t.is_empty());
+        if !has_outgoing {
+            let ident = &spans[*name];
+            errors.push(syn::Error::new(
+                ident.span(),
+                format!(
+                    "state `{name}` is non-productive: it has no outgoing transition and is not listed in `terminal`"
+                ),
+            ));
+        }
+    }
+
+    match errors.into_iter().reduce(|mut acc, err| {
+        acc.combine(err);
+        acc
+    }) {
+        Some(combined) => Err(combined),
+        None => Ok(()),
+    }
+}
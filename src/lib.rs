@@ -0,0 +1,7 @@
+// Lets the `state_machine!` macro's generated code refer to this crate by name
+// (`::statemachine::..`) even from within the crate itself.
+extern crate self as statemachine;
+
+pub mod machine;
+
+pub use statemachine_macros::state_machine;
@@ -1,6 +1,9 @@
 use std::{cell::RefCell, marker::PhantomData};
 
-use super::{error::StateMachineError, BasicStateMachine, StateWrapper};
+use super::{
+    error::StateMachineError, BasicStateMachine, MooreStateMachine, StateHook, StateWrapper,
+    TransducerStateMachine, TransitionHistory, TransitionHook, TryStateMachine,
+};
 
 pub trait StateMachineBuilder<State, Input, Transition>
 where
@@ -101,15 +104,324 @@ where
             initial_state: None,
             current_state: None,
             transition: None,
-            _marker: PhantomData::<Input>::default(),
+            _marker: PhantomData::<Input>,
+        }
+    }
+}
+
+/// This builder assembles a [`crate::machine::MooreStateMachine`], wiring up
+/// the optional entry, exit and transition hooks alongside the core
+/// `initial_state`/`transition` fields. It mirrors [`BasicStateMachineBuilder`]
+/// — the transition has the same `Fn(&State, Input) -> State` shape — but the
+/// machine it produces keeps the input around for the transition observer, so
+/// its step requires `Input: Clone`.
+pub struct MooreStateMachineBuilder<State, Input, Transition>
+where
+    Transition: Fn(&State, Input) -> State,
+    State: Clone,
+{
+    initial_state: Option<State>,
+    current_state: Option<State>,
+    transition: Option<Transition>,
+    on_enter: Option<StateHook<State>>,
+    on_exit: Option<StateHook<State>>,
+    on_transition: Option<TransitionHook<State, Input>>,
+    history_capacity: usize,
+    _marker: PhantomData<Input>,
+}
+
+impl<State, Input, Transition> MooreStateMachineBuilder<State, Input, Transition>
+where
+    Transition: Fn(&State, Input) -> State,
+    State: Clone,
+{
+    /// Enables the transition history log, keeping at most `cap` of the most
+    /// recent transitions in a ring buffer. Disabled (capacity zero) by
+    /// default, in which case nothing is recorded.
+    pub fn with_history(mut self, cap: usize) -> Self {
+        self.history_capacity = cap;
+        self
+    }
+
+    /// Registers a Moore entry action, invoked with the new state right after
+    /// the machine enters it. Optional; defaults to a no-op.
+    pub fn on_enter(mut self, hook: impl Fn(&State) + 'static) -> Self {
+        self.on_enter = Some(Box::new(hook));
+        self
+    }
+
+    /// Registers a Moore exit action, invoked with the old state right before
+    /// the machine leaves it. Optional; defaults to a no-op.
+    pub fn on_exit(mut self, hook: impl Fn(&State) + 'static) -> Self {
+        self.on_exit = Some(Box::new(hook));
+        self
+    }
+
+    /// Registers a hook invoked with `(from, input, to)` around each
+    /// transition. Optional; defaults to a no-op.
+    pub fn on_transition(mut self, hook: impl Fn(&State, &Input, &State) + 'static) -> Self {
+        self.on_transition = Some(Box::new(hook));
+        self
+    }
+}
+
+impl<State, Input, Transition> StateMachineBuilder<State, Input, Transition>
+    for MooreStateMachineBuilder<State, Input, Transition>
+where
+    Transition: Fn(&State, Input) -> State,
+    State: Clone,
+{
+    type Output = MooreStateMachine<State, Input, Transition>;
+
+    fn start() -> Self {
+        Self::default()
+    }
+
+    fn initial_state(mut self, state: State) -> Self {
+        self.initial_state = Some(state);
+        self
+    }
+
+    fn current_state(mut self, state: State) -> Self {
+        self.current_state = Some(state);
+        self
+    }
+
+    fn transition(mut self, next: Transition) -> Self {
+        self.transition = Some(next);
+        self
+    }
+
+    fn build(self) -> Result<Self::Output, Box<dyn std::error::Error>> {
+        match (self.initial_state, self.transition) {
+            (Some(initial_state), Some(transition)) => Ok(MooreStateMachine {
+                // If `current_state` in this builder is still `None`, sets
+                // `initial_state` as the current state forcibly.
+                current_state: self.current_state.unwrap_or_else(|| initial_state.clone()),
+                initial_state,
+                transition,
+                on_enter: self.on_enter,
+                on_exit: self.on_exit,
+                on_transition: self.on_transition,
+                history: TransitionHistory::new(self.history_capacity),
+                _maker: PhantomData::<Input>,
+            }),
+            (None, _) => Err(Box::new(StateMachineError::MissingField(
+                "initial_state".to_string(),
+            ))),
+            (_, None) => Err(Box::new(StateMachineError::MissingField(
+                "transition".to_string(),
+            ))),
+        }
+    }
+}
+
+impl<State, Input, Transition> Default for MooreStateMachineBuilder<State, Input, Transition>
+where
+    Transition: Fn(&State, Input) -> State,
+    State: Clone,
+{
+    fn default() -> Self {
+        MooreStateMachineBuilder {
+            initial_state: None,
+            current_state: None,
+            transition: None,
+            on_enter: None,
+            on_exit: None,
+            on_transition: None,
+            history_capacity: 0,
+            _marker: PhantomData::<Input>,
+        }
+    }
+}
+
+/// This builder assembles a [`crate::machine::TransducerStateMachine`], whose
+/// transition emits an `Output` alongside the next state. It mirrors
+/// [`BasicStateMachineBuilder`] but threads the extra `Output` type parameter
+/// through the transition closure.
+pub struct TransducerStateMachineBuilder<State, Input, Output, Transition>
+where
+    Transition: Fn(&State, Input) -> (State, Output),
+    State: Clone,
+{
+    initial_state: Option<State>,
+    current_state: Option<State>,
+    transition: Option<Transition>,
+    _marker: PhantomData<(Input, Output)>,
+}
+
+impl<State, Input, Output, Transition>
+    TransducerStateMachineBuilder<State, Input, Output, Transition>
+where
+    Transition: Fn(&State, Input) -> (State, Output),
+    State: Clone,
+{
+    /// Starts the builder.
+    pub fn start() -> Self {
+        Self::default()
+    }
+
+    /// Sets particular initial state to the state machine.
+    pub fn initial_state(mut self, state: State) -> Self {
+        self.initial_state = Some(state);
+        self
+    }
+
+    /// Sets particular state to the current state.
+    pub fn current_state(mut self, state: State) -> Self {
+        self.current_state = Some(state);
+        self
+    }
+
+    /// Sets particular transition algorithm to the state machine. The closure
+    /// returns both the next state and the emitted output.
+    pub fn transition(mut self, next: Transition) -> Self {
+        self.transition = Some(next);
+        self
+    }
+
+    /// To finish the builder. If it fails, returns [`crate::machine::error::StateMachineError`].
+    pub fn build(
+        self,
+    ) -> Result<
+        TransducerStateMachine<State, Input, Output, Transition>,
+        Box<dyn std::error::Error>,
+    > {
+        match (self.initial_state, self.transition) {
+            (Some(initial_state), Some(transition)) => Ok(TransducerStateMachine {
+                initial_state: initial_state.clone(),
+                current_state: {
+                    let current_state = self.current_state;
+                    match current_state {
+                        Some(state) => RefCell::new(StateWrapper::new(state)),
+                        None => RefCell::new(StateWrapper::new(initial_state)),
+                    }
+                },
+                transition,
+                _maker: PhantomData::<Input>,
+            }),
+            (None, _) => Err(Box::new(StateMachineError::MissingField(
+                "initial_state".to_string(),
+            ))),
+            (_, None) => Err(Box::new(StateMachineError::MissingField(
+                "transition".to_string(),
+            ))),
+        }
+    }
+}
+
+impl<State, Input, Output, Transition> Default
+    for TransducerStateMachineBuilder<State, Input, Output, Transition>
+where
+    Transition: Fn(&State, Input) -> (State, Output),
+    State: Clone,
+{
+    fn default() -> Self {
+        TransducerStateMachineBuilder {
+            initial_state: None,
+            current_state: None,
+            transition: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// This builder assembles a [`crate::machine::TryStateMachine`], whose
+/// transition may reject an input by returning `Err(Error)`. It mirrors
+/// [`BasicStateMachineBuilder`] but threads the user's `Error` type through
+/// the transition closure. Unset fields are still reported through
+/// [`StateMachineError::MissingField`].
+pub struct TryStateMachineBuilder<State, Input, Error, Transition>
+where
+    Transition: Fn(&State, Input) -> Result<State, Error>,
+    State: Clone,
+{
+    initial_state: Option<State>,
+    current_state: Option<State>,
+    transition: Option<Transition>,
+    _marker: PhantomData<(Input, Error)>,
+}
+
+impl<State, Input, Error, Transition> TryStateMachineBuilder<State, Input, Error, Transition>
+where
+    Transition: Fn(&State, Input) -> Result<State, Error>,
+    State: Clone,
+{
+    /// Starts the builder.
+    pub fn start() -> Self {
+        Self::default()
+    }
+
+    /// Sets particular initial state to the state machine.
+    pub fn initial_state(mut self, state: State) -> Self {
+        self.initial_state = Some(state);
+        self
+    }
+
+    /// Sets particular state to the current state.
+    pub fn current_state(mut self, state: State) -> Self {
+        self.current_state = Some(state);
+        self
+    }
+
+    /// Sets particular transition algorithm to the state machine. The closure
+    /// returns `Err(Error)` for inputs that are illegal in the current state.
+    pub fn transition(mut self, next: Transition) -> Self {
+        self.transition = Some(next);
+        self
+    }
+
+    /// To finish the builder. If it fails, returns [`crate::machine::error::StateMachineError`].
+    pub fn build(
+        self,
+    ) -> Result<TryStateMachine<State, Input, Error, Transition>, Box<dyn std::error::Error>>
+    {
+        match (self.initial_state, self.transition) {
+            (Some(initial_state), Some(transition)) => Ok(TryStateMachine {
+                initial_state: initial_state.clone(),
+                current_state: {
+                    let current_state = self.current_state;
+                    match current_state {
+                        Some(state) => RefCell::new(StateWrapper::new(state)),
+                        None => RefCell::new(StateWrapper::new(initial_state)),
+                    }
+                },
+                transition,
+                _maker: PhantomData::<Input>,
+            }),
+            (None, _) => Err(Box::new(StateMachineError::MissingField(
+                "initial_state".to_string(),
+            ))),
+            (_, None) => Err(Box::new(StateMachineError::MissingField(
+                "transition".to_string(),
+            ))),
+        }
+    }
+}
+
+impl<State, Input, Error, Transition> Default
+    for TryStateMachineBuilder<State, Input, Error, Transition>
+where
+    Transition: Fn(&State, Input) -> Result<State, Error>,
+    State: Clone,
+{
+    fn default() -> Self {
+        TryStateMachineBuilder {
+            initial_state: None,
+            current_state: None,
+            transition: None,
+            _marker: PhantomData,
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{BasicStateMachineBuilder, StateMachineBuilder};
-    use crate::machine::StateMachine;
+    use super::{
+        BasicStateMachineBuilder, MooreStateMachineBuilder, StateMachineBuilder,
+        TransducerStateMachineBuilder, TryStateMachineBuilder,
+    };
+    use crate::machine::{StateMachine, Transducer, TryState};
 
     #[allow(dead_code)]
     #[derive(Copy, Clone, Debug, PartialEq)]
@@ -129,6 +441,15 @@ mod test {
         Express,
     }
 
+    // A `MooreStateMachine` keeps the input around for its transition observer,
+    // so the machines that carry hooks are driven with a `Clone` input.
+    #[allow(dead_code)]
+    #[derive(Clone)]
+    enum Service {
+        Local,
+        Express,
+    }
+
     #[test]
     fn test_build() {
         // sets only initial state
@@ -186,4 +507,225 @@ mod test {
 
         assert!(sm.is_err());
     }
+
+    #[test]
+    fn test_transducer_emits_output() {
+        // A Mealy machine: the announcement depends on both the station we are
+        // leaving and the train we board.
+        let sm = TransducerStateMachineBuilder::start()
+            .initial_state(Stations::Shibuya)
+            .transition(|station, train| match (station, train) {
+                (Stations::Shibuya, Train::Local) => {
+                    (Stations::IkejiriOhashi, "Local bound for Ikejiri-Ohashi")
+                }
+                (Stations::Shibuya, Train::Express) => {
+                    (Stations::Sangendyaya, "Express bound for Sangen-jaya")
+                }
+                (Stations::Sangendyaya, Train::Express) => {
+                    (Stations::FutakoTamagawa, "Express bound for Futako-Tamagawa")
+                }
+                _ => unreachable!(),
+            })
+            .build()
+            .unwrap();
+
+        assert_eq!("Express bound for Sangen-jaya", sm.consume(Train::Express));
+        assert_eq!(Stations::Sangendyaya, sm.current_state());
+        assert_eq!(
+            "Express bound for Futako-Tamagawa",
+            sm.consume(Train::Express)
+        );
+        assert_eq!(Stations::FutakoTamagawa, sm.current_state());
+    }
+
+    #[test]
+    fn test_transducer_current_state_and_reset() {
+        let sm = TransducerStateMachineBuilder::start()
+            .initial_state(Stations::Shibuya)
+            .current_state(Stations::Sangendyaya)
+            .transition(|station, train| match (station, train) {
+                (Stations::Sangendyaya, Train::Express) => {
+                    (Stations::FutakoTamagawa, "Express bound for Futako-Tamagawa")
+                }
+                _ => unreachable!(),
+            })
+            .build()
+            .unwrap();
+
+        // `current_state` overrides the initial state.
+        assert_eq!(Stations::Sangendyaya, sm.current_state());
+        sm.consume(Train::Express);
+        assert_eq!(Stations::FutakoTamagawa, sm.current_state());
+
+        // `reset` rewinds to the initial state, not the overridden current one.
+        sm.reset();
+        assert_eq!(Stations::Shibuya, sm.current_state());
+    }
+
+    #[test]
+    fn test_transducer_fail_initial_state() {
+        let sm = TransducerStateMachineBuilder::start()
+            .transition(|station, train| match (station, train) {
+                (Stations::Shibuya, Train::Local) => (Stations::IkejiriOhashi, ()),
+                _ => unreachable!(),
+            })
+            .build();
+
+        assert!(sm.is_err());
+    }
+
+    #[test]
+    fn test_hooks_fire_in_order() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        // Records every hook callback so we can assert both ordering and the
+        // arguments each hook receives. The hooks outlive this scope, so the
+        // log is shared through an `Rc`.
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let (exit_log, trans_log, enter_log) = (log.clone(), log.clone(), log.clone());
+
+        let mut sm = MooreStateMachineBuilder::start()
+            .initial_state(Stations::Shibuya)
+            .transition(|station, service| match (station, service) {
+                (Stations::Shibuya, Service::Express) => Stations::Sangendyaya,
+                _ => unreachable!(),
+            })
+            .on_exit(move |from| exit_log.borrow_mut().push(format!("exit {from:?}")))
+            .on_transition(move |from, _input, to| {
+                trans_log
+                    .borrow_mut()
+                    .push(format!("transition {from:?}->{to:?}"))
+            })
+            .on_enter(move |to| enter_log.borrow_mut().push(format!("enter {to:?}")))
+            .build()
+            .unwrap();
+
+        sm.consume(Service::Express);
+
+        assert_eq!(
+            vec![
+                "exit Shibuya".to_string(),
+                "transition Shibuya->Sangendyaya".to_string(),
+                "enter Sangendyaya".to_string(),
+            ],
+            *log.borrow()
+        );
+        assert_eq!(Stations::Sangendyaya, sm.current_state());
+    }
+
+    #[test]
+    fn test_history_is_a_bounded_ring_buffer() {
+        let mut sm = MooreStateMachineBuilder::start()
+            .initial_state(Stations::Shibuya)
+            .with_history(2)
+            .transition(|station, service| match (station, service) {
+                (Stations::Shibuya, Service::Local) => Stations::IkejiriOhashi,
+                (Stations::IkejiriOhashi, Service::Local) => Stations::Sangendyaya,
+                (Stations::Sangendyaya, Service::Local) => Stations::KomazawaDaigaku,
+                _ => unreachable!(),
+            })
+            .build()
+            .unwrap();
+
+        sm.consume(Service::Local);
+        sm.consume(Service::Local);
+        sm.consume(Service::Local);
+
+        // Only the two most recent transitions survive the ring buffer.
+        let records: Vec<_> = sm.history().collect();
+        assert_eq!(2, records.len());
+        assert_eq!(Stations::IkejiriOhashi, records[0].from_state);
+        let last = sm.last_transition().unwrap();
+        assert_eq!(Stations::Sangendyaya, last.from_state);
+        assert_eq!(Stations::KomazawaDaigaku, last.to_state);
+    }
+
+    #[test]
+    fn test_history_disabled_by_default() {
+        let mut sm = MooreStateMachineBuilder::start()
+            .initial_state(Stations::Shibuya)
+            .transition(|station, service| match (station, service) {
+                (Stations::Shibuya, Service::Local) => Stations::IkejiriOhashi,
+                _ => unreachable!(),
+            })
+            .build()
+            .unwrap();
+
+        sm.consume(Service::Local);
+
+        assert_eq!(0, sm.history().count());
+        assert!(sm.last_transition().is_none());
+    }
+
+    #[test]
+    fn test_state_machine_macro_builds_from_table() {
+        use crate::state_machine;
+
+        // The declarative table expands to the same builder wiring the tests
+        // above write by hand. `Yoga` is the only dead end, so it is marked
+        // terminal; every other declared state has an outgoing rule and is
+        // reachable from `Shibuya`.
+        let sm = state_machine! {
+            state: Stations;
+            input: Train;
+            initial: Shibuya;
+            terminal: [Yoga];
+            Shibuya        -- Local   --> IkejiriOhashi,
+            Shibuya        -- Express --> Sangendyaya,
+            IkejiriOhashi  -- Local   --> Sangendyaya,
+            Sangendyaya    -- Local   --> KomazawaDaigaku,
+            KomazawaDaigaku -- Local  --> Sakurashinmachi,
+            Sakurashinmachi -- Local  --> Yoga,
+        }
+        .build()
+        .unwrap();
+
+        assert_eq!(Stations::Shibuya, sm.current_state());
+        sm.consume(Train::Express);
+        assert_eq!(Stations::Sangendyaya, sm.current_state());
+        sm.consume(Train::Local);
+        assert_eq!(Stations::KomazawaDaigaku, sm.current_state());
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct NoSuchTrain;
+
+    #[test]
+    fn test_try_transition_rejects_illegal_input() {
+        // The transition spells out only the legal moves and rejects the rest,
+        // so there is no need for an `unreachable!()` arm.
+        let sm = TryStateMachineBuilder::start()
+            .initial_state(Stations::Shibuya)
+            .transition(|station, train| match (station, train) {
+                (Stations::Shibuya, Train::Express) => Ok(Stations::Sangendyaya),
+                (Stations::Sangendyaya, Train::Express) => Ok(Stations::FutakoTamagawa),
+                _ => Err(NoSuchTrain),
+            })
+            .build()
+            .unwrap();
+
+        assert_eq!(Ok(Stations::Sangendyaya), sm.try_transition(Train::Express));
+        assert_eq!(Stations::Sangendyaya, sm.current_state());
+
+        // An illegal input is rejected and the current state is left untouched.
+        assert_eq!(Err(NoSuchTrain), sm.try_transition(Train::Local));
+        assert_eq!(Stations::Sangendyaya, sm.current_state());
+
+        // `reset` rewinds to the initial state.
+        sm.reset();
+        assert_eq!(Stations::Shibuya, sm.current_state());
+    }
+
+    #[test]
+    fn test_try_fail_initial_state() {
+        let sm = TryStateMachineBuilder::start()
+            .transition(|station, train| match (station, train) {
+                (Stations::Shibuya, Train::Local) => Ok(Stations::IkejiriOhashi),
+                _ => Err(NoSuchTrain),
+            })
+            .build();
+
+        assert!(sm.is_err());
+    }
 }
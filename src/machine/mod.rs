@@ -0,0 +1,399 @@
+use std::{cell::RefCell, collections::VecDeque, marker::PhantomData, time::SystemTime};
+
+pub mod builder;
+pub mod error;
+
+/// The behaviour of a plain state machine such as [`BasicStateMachine`].
+///
+/// An implementor owns its [`current_state`](StateMachine::current_state) and
+/// mutates it in place through interior mutability, so callers only ever need a
+/// shared reference to drive it. [`MooreStateMachine`], [`TransducerStateMachine`]
+/// and [`TryStateMachine`] do not implement this trait; they have their own
+/// step methods.
+pub trait StateMachine<State, Input> {
+    /// Feeds a single `input` to the machine, advancing the current state
+    /// according to the configured transition.
+    fn consume(&self, input: Input);
+
+    /// Returns a clone of the state the machine is currently in.
+    fn current_state(&self) -> State;
+
+    /// Rewinds the machine back to its initial state.
+    fn reset(&self);
+}
+
+/// A thin wrapper around the live state value.
+///
+/// It exists so the [`RefCell`] stored on the machine always borrows a single,
+/// well-defined cell rather than the bare `State`, which keeps the borrow
+/// bookkeeping in one place as the state subsystem grows.
+pub(crate) struct StateWrapper<State> {
+    state: State,
+}
+
+impl<State> StateWrapper<State> {
+    pub(crate) fn new(state: State) -> Self {
+        StateWrapper { state }
+    }
+
+    pub(crate) fn get(&self) -> &State {
+        &self.state
+    }
+
+    pub(crate) fn update(&mut self, state: State) {
+        self.state = state;
+    }
+}
+
+/// The simplest state machine: a transition function that maps the current
+/// state and an input to the next state, with no emitted output.
+///
+/// Build one through [`crate::machine::builder::BasicStateMachineBuilder`].
+pub struct BasicStateMachine<State, Input, Transition>
+where
+    Transition: Fn(&State, Input) -> State,
+    State: Clone,
+{
+    pub(crate) initial_state: State,
+    pub(crate) current_state: RefCell<StateWrapper<State>>,
+    pub(crate) transition: Transition,
+    pub(crate) _maker: PhantomData<Input>,
+}
+
+impl<State, Input, Transition> StateMachine<State, Input>
+    for BasicStateMachine<State, Input, Transition>
+where
+    Transition: Fn(&State, Input) -> State,
+    State: Clone,
+{
+    fn consume(&self, input: Input) {
+        let next = {
+            let current = self.current_state.borrow();
+            (self.transition)(current.get(), input)
+        };
+        self.current_state.borrow_mut().update(next);
+    }
+
+    fn current_state(&self) -> State {
+        self.current_state.borrow().get().clone()
+    }
+
+    fn reset(&self) {
+        self.current_state
+            .borrow_mut()
+            .update(self.initial_state.clone());
+    }
+}
+
+/// A Moore state action: a hook invoked with a single state, used for the
+/// entry and exit hooks on [`MooreStateMachine`].
+pub type StateHook<State> = Box<dyn Fn(&State)>;
+
+/// A transition observer, invoked with `(from, input, to)` around each step of
+/// a [`MooreStateMachine`].
+pub type TransitionHook<State, Input> = Box<dyn Fn(&State, &Input, &State)>;
+
+/// A single entry in a [`MooreStateMachine`]'s transition log.
+///
+/// Recording the full `input` (rather than just a label) keeps the log
+/// replayable: a captured sequence of records can be fed back through the
+/// transition to reconstruct how a node reached its current state.
+#[derive(Clone)]
+pub struct TransitionRecord<State, Input> {
+    /// The state the machine was in before the transition.
+    pub from_state: State,
+    /// The input that drove the transition.
+    pub input: Input,
+    /// The state the machine moved to.
+    pub to_state: State,
+    /// When the transition was recorded.
+    pub timestamp: SystemTime,
+}
+
+/// A bounded, append-only ring buffer of [`TransitionRecord`]s.
+///
+/// A `capacity` of zero disables recording entirely, which is the default for
+/// a [`MooreStateMachine`] that was not built with
+/// [`with_history`](crate::machine::builder::MooreStateMachineBuilder::with_history).
+pub(crate) struct TransitionHistory<State, Input> {
+    capacity: usize,
+    records: VecDeque<TransitionRecord<State, Input>>,
+}
+
+impl<State, Input> TransitionHistory<State, Input> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        TransitionHistory {
+            capacity,
+            records: VecDeque::new(),
+        }
+    }
+
+    /// Whether recording is switched on at all.
+    fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Appends a record, evicting the oldest one once `capacity` is reached.
+    fn record(&mut self, record: TransitionRecord<State, Input>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+}
+
+/// A state machine that attaches Moore-style entry and exit actions to its
+/// states, with an optional observer fired around each transition.
+///
+/// Unlike [`BasicStateMachine`], whose step only mutates the state, driving a
+/// `MooreStateMachine` runs the hooks in a fixed order — exit the old state,
+/// take the transition, enter the new one — giving effects tied to *being in*
+/// a state a well-defined place to live. Carrying the input across the
+/// transition so the observer can see it means this machine, and only this
+/// machine, requires `Input: Clone`; [`BasicStateMachine`] keeps its original
+/// bounds.
+///
+/// Because a step mutates the machine in place, it is driven through
+/// `&mut self` rather than the shared-reference interior mutability the other
+/// machines rely on.
+///
+/// Build one through [`crate::machine::builder::MooreStateMachineBuilder`].
+pub struct MooreStateMachine<State, Input, Transition>
+where
+    Transition: Fn(&State, Input) -> State,
+    State: Clone,
+{
+    pub(crate) initial_state: State,
+    pub(crate) current_state: State,
+    pub(crate) transition: Transition,
+    /// Invoked with the new state after entering it (Moore entry action).
+    pub(crate) on_enter: Option<StateHook<State>>,
+    /// Invoked with the old state before leaving it (Moore exit action).
+    pub(crate) on_exit: Option<StateHook<State>>,
+    /// Invoked with `(from, input, to)` around the transition itself.
+    pub(crate) on_transition: Option<TransitionHook<State, Input>>,
+    /// Append-only transition log; records nothing unless a capacity was set.
+    pub(crate) history: TransitionHistory<State, Input>,
+    pub(crate) _maker: PhantomData<Input>,
+}
+
+impl<State, Input, Transition> MooreStateMachine<State, Input, Transition>
+where
+    Transition: Fn(&State, Input) -> State,
+    State: Clone,
+{
+    /// Returns a clone of the state the machine is currently in.
+    pub fn current_state(&self) -> State {
+        self.current_state.clone()
+    }
+
+    /// Rewinds the machine back to its initial state.
+    pub fn reset(&mut self) {
+        self.current_state = self.initial_state.clone();
+    }
+
+    /// Iterates over the recorded transitions, oldest first.
+    ///
+    /// The iterator is empty unless the machine was built with
+    /// [`with_history`](crate::machine::builder::MooreStateMachineBuilder::with_history).
+    /// Because it borrows the machine, the borrow checker prevents holding it
+    /// across a [`consume`](MooreStateMachine::consume), which needs `&mut`.
+    pub fn history(&self) -> impl Iterator<Item = &TransitionRecord<State, Input>> {
+        self.history.records.iter()
+    }
+
+    /// Returns the most recently recorded transition, if any.
+    pub fn last_transition(&self) -> Option<&TransitionRecord<State, Input>> {
+        self.history.records.back()
+    }
+}
+
+impl<State, Input, Transition> MooreStateMachine<State, Input, Transition>
+where
+    Transition: Fn(&State, Input) -> State,
+    State: Clone,
+    // The transition observer sees the input alongside the states, so a step
+    // has to keep a copy of it around the transition.
+    Input: Clone,
+{
+    /// Feeds a single `input` to the machine, running the hooks in order:
+    /// exit the old state, take the transition, enter the new one.
+    pub fn consume(&mut self, input: Input) {
+        // Snapshot what the history log needs before the input is consumed by
+        // the transition; skipped entirely when history is disabled so the
+        // common path pays no extra clone.
+        let recorded = if self.history.is_enabled() {
+            Some((self.current_state.clone(), input.clone()))
+        } else {
+            None
+        };
+
+        if let Some(on_exit) = &self.on_exit {
+            on_exit(&self.current_state);
+        }
+
+        let to = match &self.on_transition {
+            Some(on_transition) => {
+                let to = (self.transition)(&self.current_state, input.clone());
+                on_transition(&self.current_state, &input, &to);
+                to
+            }
+            None => (self.transition)(&self.current_state, input),
+        };
+
+        if let Some((from_state, input)) = recorded {
+            self.history.record(TransitionRecord {
+                from_state,
+                input,
+                to_state: to.clone(),
+                timestamp: SystemTime::now(),
+            });
+        }
+
+        self.current_state = to;
+
+        if let Some(on_enter) = &self.on_enter {
+            on_enter(&self.current_state);
+        }
+    }
+}
+
+/// A state machine whose transition may reject an input.
+///
+/// The associated `Error` type is carried on the machine, mirroring the way
+/// [`Transducer`] carries its `Output`, so a fallible machine can be named
+/// without repeating the closure's error type at every use site.
+pub trait TryState<State, Input> {
+    /// The error produced when an input is illegal in the current state.
+    type Error;
+
+    /// Feeds a single `input` to the machine. On success the current state is
+    /// advanced and a clone of the new state is returned; on failure the
+    /// current state is left untouched and the error is returned.
+    fn try_transition(&self, input: Input) -> Result<State, Self::Error>;
+
+    /// Returns a clone of the state the machine is currently in.
+    fn current_state(&self) -> State;
+
+    /// Rewinds the machine back to its initial state.
+    fn reset(&self);
+}
+
+/// A state machine whose transition returns `Result<State, E>`, turning an
+/// undefined transition into a recoverable, inspectable failure rather than a
+/// panic.
+///
+/// This removes the need for the `_ => unreachable!()` arm every hand-written
+/// transition otherwise has to carry: inputs that are illegal in the current
+/// state become an `Err(E)` that leaves the machine where it was.
+///
+/// Build one through [`crate::machine::builder::TryStateMachineBuilder`].
+pub struct TryStateMachine<State, Input, Error, Transition>
+where
+    Transition: Fn(&State, Input) -> Result<State, Error>,
+    State: Clone,
+{
+    pub(crate) initial_state: State,
+    pub(crate) current_state: RefCell<StateWrapper<State>>,
+    pub(crate) transition: Transition,
+    pub(crate) _maker: PhantomData<Input>,
+}
+
+impl<State, Input, Error, Transition> TryState<State, Input>
+    for TryStateMachine<State, Input, Error, Transition>
+where
+    Transition: Fn(&State, Input) -> Result<State, Error>,
+    State: Clone,
+{
+    type Error = Error;
+
+    fn try_transition(&self, input: Input) -> Result<State, Error> {
+        let next = {
+            let current = self.current_state.borrow();
+            (self.transition)(current.get(), input)?
+        };
+        self.current_state.borrow_mut().update(next.clone());
+        Ok(next)
+    }
+
+    fn current_state(&self) -> State {
+        self.current_state.borrow().get().clone()
+    }
+
+    fn reset(&self) {
+        self.current_state
+            .borrow_mut()
+            .update(self.initial_state.clone());
+    }
+}
+
+/// A Mealy-style finite-state transducer: every transition produces both the
+/// next state and an `Output` value that is handed back to the caller.
+///
+/// Unlike [`BasicStateMachine`], whose effect on the world is only observable
+/// by diffing the state before and after, a transducer lets the transition
+/// itself emit a command — a log line, a protocol message, a UI event — so
+/// side effects can be driven directly as the machine runs. The degenerate
+/// case `Output = ()` is exactly [`BasicStateMachine`].
+///
+/// Build one through [`crate::machine::builder::TransducerStateMachineBuilder`].
+pub struct TransducerStateMachine<State, Input, Output, Transition>
+where
+    Transition: Fn(&State, Input) -> (State, Output),
+    State: Clone,
+{
+    pub(crate) initial_state: State,
+    pub(crate) current_state: RefCell<StateWrapper<State>>,
+    pub(crate) transition: Transition,
+    pub(crate) _maker: PhantomData<Input>,
+}
+
+/// A state machine that emits an [`Output`](Transducer::Output) on every step.
+///
+/// The associated `Output` type is carried on the machine so a transducer can
+/// be named without repeating the closure's return type at every use site.
+pub trait Transducer<State, Input> {
+    /// The value emitted by each transition.
+    type Output;
+
+    /// Feeds a single `input` to the machine, advancing the current state and
+    /// returning the [`Output`](Transducer::Output) produced by the transition.
+    fn consume(&self, input: Input) -> Self::Output;
+
+    /// Returns a clone of the state the machine is currently in.
+    fn current_state(&self) -> State;
+
+    /// Rewinds the machine back to its initial state.
+    fn reset(&self);
+}
+
+impl<State, Input, Output, Transition> Transducer<State, Input>
+    for TransducerStateMachine<State, Input, Output, Transition>
+where
+    Transition: Fn(&State, Input) -> (State, Output),
+    State: Clone,
+{
+    type Output = Output;
+
+    fn consume(&self, input: Input) -> Output {
+        let (next, output) = {
+            let current = self.current_state.borrow();
+            (self.transition)(current.get(), input)
+        };
+        self.current_state.borrow_mut().update(next);
+        output
+    }
+
+    fn current_state(&self) -> State {
+        self.current_state.borrow().get().clone()
+    }
+
+    fn reset(&self) {
+        self.current_state
+            .borrow_mut()
+            .update(self.initial_state.clone());
+    }
+}